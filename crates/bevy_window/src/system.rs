@@ -3,7 +3,7 @@ use crate::{PrimaryWindow, SurfaceToken, Window, WindowCloseRequested};
 use bevy_app::AppExit;
 use bevy_ecs::prelude::*;
 use bevy_input::{keyboard::KeyCode, Input};
-use bevy_utils::HashSet;
+use bevy_utils::{HashMap, HashSet};
 
 /// Exit the application when there are no open windows.
 ///
@@ -34,58 +34,317 @@ pub fn exit_on_primary_closed(
     }
 }
 
+/// Sent by [`close_when_requested`] when a window has received a
+/// [`WindowCloseRequested`] event, before it is despawned.
+///
+/// [`WindowPlugin`] must register this event (`app.add_event::<WindowClosing>()`)
+/// for [`close_when_requested`] to run without panicking.
+///
+/// A freshly requested close is always deferred by at least one frame, so
+/// app code can respond to this event by inserting [`BlockWindowClose`] on
+/// the window entity any time before [`close_when_requested`] runs again to
+/// veto the close, e.g. to show an "unsaved changes" confirmation dialog.
+/// Once done deciding, resolve the block with [`CloseDecision::resolve`].
+///
+/// [`WindowPlugin`]: crate::WindowPlugin
+#[derive(Event, Debug, Clone, Copy)]
+pub struct WindowClosing {
+    /// The window that has requested to be closed.
+    pub window: Entity,
+}
+
+/// Blocks [`close_when_requested`] from despawning this window.
+///
+/// Insert this on a window entity in response to [`WindowClosing`] to pause
+/// the close; remove it (directly, or via [`CloseDecision::resolve`]) once
+/// the app has decided whether the window may close.
+#[derive(Component, Debug, Clone)]
+pub struct BlockWindowClose {
+    /// Human readable reason the close is currently blocked, useful for
+    /// displaying to the user or for debugging.
+    pub reason: String,
+}
+
+impl BlockWindowClose {
+    /// Block the window's pending close, recording `reason` for debugging or
+    /// display purposes.
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Marker inserted by [`CloseDecision::Deny`] to tell [`close_when_requested`]
+/// to drop the window's pending close request entirely, rather than letting
+/// it proceed once unblocked.
+#[derive(Component, Debug, Clone, Copy)]
+struct CanceledWindowClose;
+
+/// The outcome of a [`BlockWindowClose`] raised by a [`WindowClosing`] veto,
+/// for confirmation systems that resolve the block asynchronously (e.g. after
+/// the user answers a dialog).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseDecision {
+    /// Let the close proceed; [`close_when_requested`] will despawn the
+    /// window once any other gates are satisfied.
+    Allow,
+    /// Cancel the close; the window stays open and its
+    /// [`WindowCloseRequested`] is dropped.
+    Deny,
+}
+
+impl CloseDecision {
+    /// Apply this decision to `window`, removing its [`BlockWindowClose`].
+    pub fn resolve(self, commands: &mut Commands, window: Entity) {
+        let mut entity = commands.entity(window);
+        entity.remove::<BlockWindowClose>();
+        if self == CloseDecision::Deny {
+            entity.insert(CanceledWindowClose);
+        }
+    }
+}
+
+/// Name of the gate that [`close_when_requested`] registers on a window's
+/// [`WindowCloseGates`] on behalf of its [`SurfaceToken`], so the renderer's
+/// readiness shows up in the same registry as every other gate instead of
+/// living behind a separate, invisible check.
+pub const RENDERER_SURFACE_GATE: &str = "renderer-surface";
+
+/// A named readiness gate that can block a window from being despawned by
+/// [`close_when_requested`].
+///
+/// Any number of plugins can register a gate under their own name (e.g.
+/// `"renderer-surface"`, `"autosave"`, `"network-flush"`) and report their
+/// own readiness independently; the window is only despawned once every
+/// registered gate reports ready. A window with no registered gates is
+/// considered ready to close.
+///
+/// On a window that has this component, the renderer's [`SurfaceToken`] is
+/// just another gate in this registry: [`close_when_requested`] registers it
+/// under [`RENDERER_SURFACE_GATE`] and syncs its readiness each time it
+/// checks the window's pending, unblocked close, so from then on code
+/// inspecting the window's gates (e.g. to explain why a window hasn't closed
+/// yet) sees the renderer alongside every other gate. Before that first
+/// check the renderer's gate is absent. A window without this component has
+/// its [`SurfaceToken`] checked directly instead.
+#[derive(Component, Debug, Default, Clone)]
+pub struct WindowCloseGates {
+    gates: HashMap<String, bool>,
+}
+
+impl WindowCloseGates {
+    /// Register a new gate under `name`, starting out not ready.
+    ///
+    /// Does nothing if a gate with this name is already registered.
+    pub fn register(&mut self, name: impl Into<String>) -> &mut Self {
+        self.gates.entry(name.into()).or_insert(false);
+        self
+    }
+
+    /// Report whether the gate `name` is ready to allow the window to close.
+    ///
+    /// Does nothing if no gate with this name has been registered.
+    pub fn set_ready(&mut self, name: &str, ready: bool) {
+        if let Some(gate) = self.gates.get_mut(name) {
+            *gate = ready;
+        }
+    }
+
+    /// Returns `true` once every registered gate reports ready.
+    pub fn all_ready(&self) -> bool {
+        self.gates.values().all(|ready| *ready)
+    }
+}
+
+/// Tracks windows that have an in-flight close request, so other systems can
+/// inspect which windows are waiting to close (e.g. to show a "closing…"
+/// indicator in a UI).
+///
+/// Populated and drained by [`close_when_requested`]. [`WindowPlugin`] must
+/// initialize this resource (`app.init_resource::<PendingCloseWindows>()`)
+/// for [`close_when_requested`] to run without panicking.
+///
+/// [`WindowPlugin`]: crate::WindowPlugin
+#[derive(Resource, Debug, Default)]
+pub struct PendingCloseWindows {
+    windows: HashSet<Entity>,
+}
+
+impl PendingCloseWindows {
+    /// Iterate over the windows that are currently waiting to close.
+    pub fn pending_close_windows(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.windows.iter().copied()
+    }
+}
+
 /// Close windows in response to [`WindowCloseRequested`] (e.g.  when the close button is pressed).
 ///
 /// This system is added by the [`WindowPlugin`] in the default configuration.
 /// To disable this behavior, set `close_when_requested` (on the [`WindowPlugin`]) to `false`.
 /// Ensure that you read the caveats documented on that field if doing so.
 ///
+/// Before despawning, this system sends [`WindowClosing`] so app code can
+/// veto the close with [`BlockWindowClose`]; see that type for details. A
+/// freshly requested close is always stashed in [`PendingCloseWindows`] for
+/// at least one frame rather than despawned on the spot, so a veto system
+/// ordered either before or after this one has a full frame to insert
+/// [`BlockWindowClose`] before the window can actually go away. From then on,
+/// a window only despawns once every gate in its [`WindowCloseGates`] (if
+/// any) reports ready. On a window with [`WindowCloseGates`], the renderer's
+/// [`SurfaceToken`] is synced into that registry under
+/// [`RENDERER_SURFACE_GATE`]; on a window without it, the [`SurfaceToken`]
+/// (if any) is checked directly.
+///
 /// [`WindowPlugin`]: crate::WindowPlugin
 pub fn close_when_requested(
     mut commands: Commands,
-    tokens: Query<&SurfaceToken>,
+    mut windows: Query<(Option<&SurfaceToken>, Option<&mut WindowCloseGates>)>,
+    blocked: Query<(), With<BlockWindowClose>>,
+    canceled: Query<Entity, With<CanceledWindowClose>>,
     mut closed: EventReader<WindowCloseRequested>,
-    mut waiting_to_close: Local<HashSet<Entity>>,
+    mut closing: EventWriter<WindowClosing>,
+    mut pending_close: ResMut<PendingCloseWindows>,
 ) {
-    for event in closed.read() {
-        if let Ok(token) = tokens.get(event.window) {
-            // Check if that is okay
-            if token.is_safe_to_close_window() {
-                commands.entity(event.window).despawn();
-            } else {
-                // Stash for later when the renderer cleans up the surface
-                waiting_to_close.insert(event.window);
+    // Sync the renderer's `SurfaceToken` into `WindowCloseGates` as an
+    // ordinary named gate, and report whether every gate on `window_entity`
+    // is now ready.
+    let mut is_ready_to_close = |window_entity: Entity| {
+        let Ok((token, gates)) = windows.get_mut(window_entity) else {
+            return true;
+        };
+
+        match gates {
+            Some(mut gates) => {
+                if let Some(token) = token {
+                    let ready = token.is_safe_to_close_window();
+                    // `register`/`set_ready` take `&mut self`, so calling them
+                    // unconditionally would mark `WindowCloseGates` `Changed`
+                    // every frame even when nothing actually changed. Check
+                    // first so change detection only fires on real
+                    // transitions (e.g. for a "closing…" UI indicator).
+                    if !gates.gates.contains_key(RENDERER_SURFACE_GATE) {
+                        gates.register(RENDERER_SURFACE_GATE);
+                    }
+                    if gates.gates.get(RENDERER_SURFACE_GATE) != Some(&ready) {
+                        gates.set_ready(RENDERER_SURFACE_GATE, ready);
+                    }
+                }
+                gates.all_ready()
             }
+            None => token.map_or(true, |token| token.is_safe_to_close_window()),
         }
+    };
+
+    for window_entity in &canceled {
+        pending_close.windows.remove(&window_entity);
+        commands.entity(window_entity).remove::<CanceledWindowClose>();
     }
 
-    waiting_to_close.retain(|window_entity| {
-        if let Ok(token) = tokens.get(*window_entity) {
-            if token.is_safe_to_close_window() {
-                commands.entity(*window_entity).despawn();
-                return false;
-            }
+    // Resolve windows that were already pending as of a previous frame, now
+    // that every veto system has had a full frame to react to their
+    // `WindowClosing` event.
+    pending_close.windows.retain(|window_entity| {
+        if blocked.contains(*window_entity) {
+            return true;
+        }
+
+        if is_ready_to_close(*window_entity) {
+            commands.entity(*window_entity).despawn();
+            return false;
         }
 
         true
-    })
+    });
+
+    // Freshly requested closes are never despawned in the same pass that
+    // sends their `WindowClosing`: a listener ordered after this system
+    // could otherwise have its `BlockWindowClose` insertion land on an
+    // entity that's already gone, and a listener ordered before this system
+    // hasn't seen the event yet. Stashing unconditionally guarantees a veto
+    // system always gets a chance to block the close before the next pass
+    // resolves it above.
+    for event in closed.read() {
+        closing.send(WindowClosing {
+            window: event.window,
+        });
+
+        pending_close.windows.insert(event.window);
+    }
+}
+
+/// What [`close_on_esc`] should do to the focused window once its configured
+/// key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CloseAction {
+    /// Despawn the focused window entity directly.
+    #[default]
+    DespawnWindow,
+    /// Send a [`WindowCloseRequested`] for the focused window, routing
+    /// through `close_when_requested` (and any veto systems) instead of
+    /// closing immediately.
+    RequestClose,
+    /// Send an [`AppExit`], exiting the whole application.
+    AppExit,
 }
 
-/// Close the focused window whenever the escape key (<kbd>Esc</kbd>) is pressed
+/// Configures the key and behavior used by [`close_on_esc`].
 ///
-/// This is useful for examples or prototyping.
+/// Insert this resource to rebind the key away from the default
+/// <kbd>Escape</kbd>, or to change what happens when it's pressed. Without
+/// this resource, `close_on_esc` falls back to its default configuration.
+#[derive(Resource, Debug, Clone)]
+pub struct CloseOnKey {
+    /// Key that triggers [`Self::action`] on the focused window.
+    pub key: KeyCode,
+    /// What should happen once [`Self::key`] is pressed.
+    pub action: CloseAction,
+}
+
+impl Default for CloseOnKey {
+    fn default() -> Self {
+        Self {
+            key: KeyCode::Escape,
+            action: CloseAction::default(),
+        }
+    }
+}
+
+/// Close the focused window whenever the configured key (<kbd>Esc</kbd> by
+/// default) is pressed.
+///
+/// This is useful for examples or prototyping. Insert [`CloseOnKey`] to
+/// rebind the key or change what closing does, e.g. to raise an
+/// [`AppExit`] or go through the [`WindowCloseRequested`] pipeline instead of
+/// despawning directly.
 pub fn close_on_esc(
     mut commands: Commands,
     focused_windows: Query<(Entity, &Window)>,
     input: Res<Input<KeyCode>>,
+    close_on_key: Option<Res<CloseOnKey>>,
+    mut window_close_requested: EventWriter<WindowCloseRequested>,
+    mut app_exit: EventWriter<AppExit>,
 ) {
+    let default_config = CloseOnKey::default();
+    let config = close_on_key.as_deref().unwrap_or(&default_config);
+
     for (window, focus) in focused_windows.iter() {
         if !focus.focused {
             continue;
         }
 
-        if input.just_pressed(KeyCode::Escape) {
-            commands.entity(window).despawn();
+        if input.just_pressed(config.key) {
+            match config.action {
+                CloseAction::DespawnWindow => {
+                    commands.entity(window).despawn();
+                }
+                CloseAction::RequestClose => {
+                    window_close_requested.send(WindowCloseRequested { window });
+                }
+                CloseAction::AppExit => {
+                    app_exit.send(AppExit);
+                }
+            }
         }
     }
 }
@@ -100,3 +359,197 @@ pub fn fixup_window_surface(
         commands.entity(entity).insert(SurfaceToken::default());
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_app::{App, Update};
+    use bevy_ecs::{event::Events, system::CommandQueue};
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_event::<WindowCloseRequested>();
+        app.add_event::<WindowClosing>();
+        app.add_event::<AppExit>();
+        app.init_resource::<PendingCloseWindows>();
+        app.add_systems(Update, close_when_requested);
+        app
+    }
+
+    fn resolve(app: &mut App, decision: CloseDecision, window: Entity) {
+        let mut queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut queue, &app.world);
+        decision.resolve(&mut commands, window);
+        queue.apply(&mut app.world);
+    }
+
+    #[test]
+    fn close_request_is_not_despawned_in_the_same_frame() {
+        let mut app = test_app();
+        let window = app.world.spawn(Window::default()).id();
+
+        app.world
+            .resource_mut::<Events<WindowCloseRequested>>()
+            .send(WindowCloseRequested { window });
+        app.update();
+
+        assert!(
+            app.world.get_entity(window).is_some(),
+            "a freshly requested close must not despawn the window on the same frame"
+        );
+        assert!(app
+            .world
+            .resource::<PendingCloseWindows>()
+            .pending_close_windows()
+            .any(|pending| pending == window));
+
+        app.update();
+
+        assert!(
+            app.world.get_entity(window).is_none(),
+            "an unblocked window should despawn on the frame following its request"
+        );
+    }
+
+    #[test]
+    fn block_window_close_holds_the_window_open_across_frames() {
+        let mut app = test_app();
+        let window = app.world.spawn(Window::default()).id();
+
+        app.world
+            .resource_mut::<Events<WindowCloseRequested>>()
+            .send(WindowCloseRequested { window });
+        app.update();
+
+        app.world
+            .entity_mut(window)
+            .insert(BlockWindowClose::new("unsaved changes"));
+
+        for _ in 0..3 {
+            app.update();
+            assert!(
+                app.world.get_entity(window).is_some(),
+                "a window with an unresolved BlockWindowClose must stay open"
+            );
+        }
+
+        resolve(&mut app, CloseDecision::Allow, window);
+        app.update();
+
+        assert!(
+            app.world.get_entity(window).is_none(),
+            "CloseDecision::Allow should let the close proceed once unblocked"
+        );
+    }
+
+    #[test]
+    fn close_decision_deny_cancels_the_close_permanently() {
+        let mut app = test_app();
+        let window = app.world.spawn(Window::default()).id();
+
+        app.world
+            .resource_mut::<Events<WindowCloseRequested>>()
+            .send(WindowCloseRequested { window });
+        app.update();
+
+        app.world
+            .entity_mut(window)
+            .insert(BlockWindowClose::new("confirming"));
+        app.update();
+
+        resolve(&mut app, CloseDecision::Deny, window);
+        app.update();
+
+        assert!(
+            app.world.get_entity(window).is_some(),
+            "CloseDecision::Deny should cancel the close, not despawn the window"
+        );
+        assert!(
+            !app.world
+                .resource::<PendingCloseWindows>()
+                .pending_close_windows()
+                .any(|pending| pending == window),
+            "a denied close must not re-enter PendingCloseWindows"
+        );
+
+        // A further pass shouldn't resurrect the canceled close.
+        app.update();
+        assert!(app.world.get_entity(window).is_some());
+    }
+
+    fn esc_test_app(config: CloseOnKey) -> (App, Entity) {
+        let mut app = App::new();
+        app.add_event::<WindowCloseRequested>();
+        app.add_event::<AppExit>();
+        app.init_resource::<Input<KeyCode>>();
+        app.insert_resource(config);
+        app.add_systems(Update, close_on_esc);
+
+        let window = app
+            .world
+            .spawn(Window {
+                focused: true,
+                ..Default::default()
+            })
+            .id();
+
+        (app, window)
+    }
+
+    fn press(app: &mut App, key: KeyCode) {
+        app.world.resource_mut::<Input<KeyCode>>().press(key);
+        app.update();
+    }
+
+    #[test]
+    fn close_on_esc_despawns_the_focused_window() {
+        let (mut app, window) = esc_test_app(CloseOnKey {
+            key: KeyCode::Q,
+            action: CloseAction::DespawnWindow,
+        });
+
+        press(&mut app, KeyCode::Q);
+
+        assert!(app.world.get_entity(window).is_none());
+    }
+
+    #[test]
+    fn close_on_esc_requests_close_instead_of_despawning() {
+        let (mut app, window) = esc_test_app(CloseOnKey {
+            key: KeyCode::Q,
+            action: CloseAction::RequestClose,
+        });
+
+        press(&mut app, KeyCode::Q);
+
+        assert!(
+            app.world.get_entity(window).is_some(),
+            "RequestClose must not despawn the window directly"
+        );
+        assert_eq!(
+            app.world
+                .resource::<Events<WindowCloseRequested>>()
+                .iter_current_update_events()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn close_on_esc_sends_app_exit() {
+        let (mut app, _window) = esc_test_app(CloseOnKey {
+            key: KeyCode::Q,
+            action: CloseAction::AppExit,
+        });
+
+        press(&mut app, KeyCode::Q);
+
+        assert_eq!(
+            app.world
+                .resource::<Events<AppExit>>()
+                .iter_current_update_events()
+                .count(),
+            1
+        );
+    }
+}